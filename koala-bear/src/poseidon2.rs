@@ -1,9 +1,14 @@
 //! Implementation of Poseidon2, see: https://eprint.iacr.org/2023/323
-use p3_field::PrimeField32;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use p3_field::{FieldAlgebra, PrimeField32};
 use p3_poseidon2::{
     external_final_permute_state, external_initial_permute_state, internal_permute_state,
-    ExternalLayer, InternalLayer, Poseidon2PackedTypesAndConstants,
+    ExternalLayer, ExternalLayerConstants, InternalLayer, Poseidon2,
+    Poseidon2PackedTypesAndConstants,
 };
+use p3_symmetric::Permutation;
 
 use crate::{monty_reduce, to_koalabear_array, KoalaBear};
 
@@ -47,9 +52,6 @@ pub const POSEIDON2_INTERNAL_MATRIX_DIAG_16_KOALABEAR_MONTY: [KoalaBear; 16] =
         1 << 15,
     ]);
 
-const POSEIDON2_INTERNAL_MATRIX_DIAG_16_MONTY_SHIFTS: [u8; 15] =
-    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15];
-
 pub const POSEIDON2_INTERNAL_MATRIX_DIAG_24_KOALABEAR_MONTY: [KoalaBear; 24] =
     to_koalabear_array([
         KoalaBear::ORDER_U32 - 2,
@@ -78,9 +80,172 @@ pub const POSEIDON2_INTERNAL_MATRIX_DIAG_24_KOALABEAR_MONTY: [KoalaBear; 24] =
         1 << 23,
     ]);
 
-const POSEIDON2_INTERNAL_MATRIX_DIAG_24_MONTY_SHIFTS: [u8; 23] = [
-    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 23,
-];
+/// Derive the shift-based multipliers `permute_mut` expects from a diffusion diagonal: the
+/// diagonal's first entry (`-2`) is handled separately by `permute_mut`, and every other
+/// entry is a power of two, represented here by its exponent so the diffusion step can use a
+/// shift instead of a full field multiplication.
+///
+/// Returns an `N`-element array (rather than `N - 1`, since const generics can't express that
+/// here) with a meaningless entry at index `0`; callers index from `1` as `permute_mut`
+/// expects. Computed on the stack so permuting never allocates.
+fn diagonal_shifts<const N: usize>(diagonal: &[KoalaBear; N]) -> [u8; N] {
+    let mut shifts = [0u8; N];
+    for (shift, entry) in shifts.iter_mut().zip(diagonal).skip(1) {
+        *shift = entry.as_canonical_u32().trailing_zeros() as u8;
+    }
+    shifts
+}
+
+/// A Poseidon2 parameter set for a field/S-box/width combination: the internal diffusion
+/// diagonal plus the round counts needed to generate round constants.
+///
+/// Implementors of this trait don't need to hand-transcribe tables: the external and
+/// internal round constants are generated deterministically by [`GrainLfsr`], following the
+/// canonical Grain LFSR construction from the Poseidon paper
+/// (<https://eprint.iacr.org/2019/458>, section "Round constants"). Only the diffusion
+/// diagonal (which the LFSR doesn't determine) and the round counts need to be supplied.
+pub trait Poseidon2Spec<F: PrimeField32, const WIDTH: usize, const D: u64> {
+    /// Number of full (external) rounds, split evenly between the initial and final halves.
+    const ROUNDS_F: usize;
+    /// Number of partial (internal) rounds.
+    const ROUNDS_P: usize;
+
+    /// The internal diffusion diagonal: the vector `v` of the internal matrix `1 + D(v)`.
+    fn internal_diagonal() -> [F; WIDTH];
+
+    /// Generate the `ROUNDS_F` external round constants and `ROUNDS_P` internal round
+    /// constants together, from a single continuous LFSR, drawing in the same order the
+    /// rounds are actually applied: the first `ROUNDS_F / 2` external (full) rounds, then
+    /// all `ROUNDS_P` internal (partial) rounds, then the final `ROUNDS_F / 2` external
+    /// rounds. [`Self::external_round_constants`] and [`Self::internal_round_constants`]
+    /// each re-run the whole draw and keep only their half, rather than splitting one
+    /// stream, so they can't silently disagree about where the other's prefix ends.
+    ///
+    /// Each external round draws `WIDTH` elements (one per state lane) and each internal
+    /// round draws exactly one (every internal round only ever adds a constant to lane 0),
+    /// rather than drawing a full `WIDTH`-element vector per internal round and discarding
+    /// all but the first. The total constants drawn, `WIDTH * ROUNDS_F + ROUNDS_P`, matches
+    /// the count Poseidon2 parameter generation is documented to use; the discard-the-rest
+    /// convention would instead draw `WIDTH * (ROUNDS_F + ROUNDS_P)` and desynchronize every
+    /// constant after the first internal round.
+    fn round_constants() -> (Vec<[F; WIDTH]>, Vec<F>) {
+        assert_eq!(
+            Self::ROUNDS_F % 2,
+            0,
+            "ROUNDS_F must split evenly into initial/terminal halves"
+        );
+        let mut lfsr = GrainLfsr::new::<F>(
+            D,
+            WIDTH as u64,
+            Self::ROUNDS_F as u64,
+            Self::ROUNDS_P as u64,
+        );
+        let half_f = Self::ROUNDS_F / 2;
+        let mut external: Vec<[F; WIDTH]> = (0..half_f)
+            .map(|_| core::array::from_fn(|_| lfsr.next_field_element::<F>()))
+            .collect();
+        let internal: Vec<F> = (0..Self::ROUNDS_P)
+            .map(|_| lfsr.next_field_element::<F>())
+            .collect();
+        external
+            .extend((0..half_f).map(|_| core::array::from_fn(|_| lfsr.next_field_element::<F>())));
+        (external, internal)
+    }
+
+    /// Generate the `ROUNDS_F` external round constants.
+    fn external_round_constants() -> Vec<[F; WIDTH]> {
+        Self::round_constants().0
+    }
+
+    /// Generate the `ROUNDS_P` internal round constants.
+    fn internal_round_constants() -> Vec<F> {
+        Self::round_constants().1
+    }
+}
+
+/// The Grain LFSR used by the Poseidon paper to deterministically derive round constants
+/// from a field/S-box/width/round descriptor, so two implementations of the same parameters
+/// always agree without shipping a table.
+///
+/// The register is seeded with (MSB first): 2 bits for the field type (`1` for a prime
+/// field), 4 bits for the S-box degree, 12 bits for the field's bit length, 12 bits for the
+/// width, 10 bits for the number of full rounds, 10 bits for the number of partial rounds,
+/// and 30 bits set to `1`. The first 160 raw clocked bits are then discarded as warm-up, and
+/// every bit drawn afterwards (including by [`Self::next_bits`]) goes through the
+/// self-shrinking conditioning in [`Self::next_bit`].
+struct GrainLfsr {
+    // Only the low 80 bits are used.
+    state: u128,
+}
+
+impl GrainLfsr {
+    fn new<F: PrimeField32>(sbox_degree: u64, width: u64, rounds_f: u64, rounds_p: u64) -> Self {
+        // `state` bit 0 holds the first (most significant) bit of the field-type descriptor,
+        // with each field's own bits laid out MSB-first from there; this is the end the taps
+        // in `clock_bit` are relative to, and the end that gets clocked out first below.
+        let mut state: u128 = 0;
+        let mut pos = 0u32;
+        let mut push = |value: u64, len: u32| {
+            for j in 0..len {
+                let bit = (value >> (len - 1 - j)) & 1;
+                state |= u128::from(bit) << (pos + j);
+            }
+            pos += len;
+        };
+        push(1, 2); // Field type: prime field.
+        push(sbox_degree, 4);
+        push(u64::from(u32::BITS - F::ORDER_U32.leading_zeros()), 12);
+        push(width, 12);
+        push(rounds_f, 10);
+        push(rounds_p, 10);
+        push((1 << 30) - 1, 30);
+        debug_assert_eq!(pos, 80);
+
+        let mut lfsr = Self { state };
+        for _ in 0..160 {
+            lfsr.clock_bit();
+        }
+        lfsr
+    }
+
+    /// Clock the register once, returning the freshly-computed feedback bit (not the bit
+    /// shifted out the other end, which the paper's generator never looks at again).
+    fn clock_bit(&mut self) -> u64 {
+        let bit = |i: u32| (self.state >> i) & 1;
+        let feedback = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+        self.state = (self.state >> 1) | (feedback << 79);
+        feedback as u64
+    }
+
+    /// Draw one output bit via the canonical Grain self-shrinking generator: clock the
+    /// register twice, and if the first of the pair is `0`, discard both and retry; otherwise
+    /// emit the second.
+    fn next_bit(&mut self) -> u64 {
+        loop {
+            let keep = self.clock_bit();
+            let out = self.clock_bit();
+            if keep == 1 {
+                return out;
+            }
+        }
+    }
+
+    /// Draw `num_bits` bits, MSB first, as a single integer.
+    fn next_bits(&mut self, num_bits: u32) -> u64 {
+        (0..num_bits).fold(0u64, |value, _| (value << 1) | self.next_bit())
+    }
+
+    /// Draw a uniformly random field element, rejecting (and redrawing) samples `>= p`.
+    fn next_field_element<F: PrimeField32>(&mut self) -> F {
+        let num_bits = u32::BITS - F::ORDER_U32.leading_zeros();
+        loop {
+            let candidate = self.next_bits(num_bits) as u32;
+            if candidate < F::ORDER_U32 {
+                return F::from_canonical_u32(candidate);
+            }
+        }
+    }
+}
 
 fn permute_mut<const N: usize>(state: &mut [KoalaBear; N], shifts: &[u8]) {
     let part_sum: u64 = state.iter().skip(1).map(|x| x.value as u64).sum();
@@ -100,11 +265,58 @@ fn permute_mut<const N: usize>(state: &mut [KoalaBear; N], shifts: &[u8]) {
 #[derive(Debug, Clone, Default)]
 pub struct Poseidon2KoalaBearPackedConstants;
 
+/// The canonical Poseidon2 parameter set for `KoalaBear` at width 16, matching the
+/// hand-optimized diagonal above. A field/width this crate doesn't hand-tune a diagonal for
+/// can still implement [`Poseidon2Spec`] and get its round constants from [`GrainLfsr`] for
+/// free.
 #[derive(Debug, Clone, Default)]
-pub struct DiffusionMatrixKoalaBear;
+pub struct KoalaBearPoseidon2Spec16;
 
-impl<const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 16, D>
-    for DiffusionMatrixKoalaBear
+impl<const D: u64> Poseidon2Spec<KoalaBear, 16, D> for KoalaBearPoseidon2Spec16 {
+    const ROUNDS_F: usize = 8;
+    const ROUNDS_P: usize = 20;
+
+    fn internal_diagonal() -> [KoalaBear; 16] {
+        POSEIDON2_INTERNAL_MATRIX_DIAG_16_KOALABEAR_MONTY
+    }
+}
+
+/// The canonical Poseidon2 parameter set for `KoalaBear` at width 24, matching the
+/// hand-optimized diagonal above.
+#[derive(Debug, Clone, Default)]
+pub struct KoalaBearPoseidon2Spec24;
+
+impl<const D: u64> Poseidon2Spec<KoalaBear, 24, D> for KoalaBearPoseidon2Spec24 {
+    const ROUNDS_F: usize = 8;
+    const ROUNDS_P: usize = 23;
+
+    fn internal_diagonal() -> [KoalaBear; 24] {
+        POSEIDON2_INTERNAL_MATRIX_DIAG_24_KOALABEAR_MONTY
+    }
+}
+
+/// The diffusion (internal) layer of a KoalaBear Poseidon2 permutation, generic over the
+/// [`Poseidon2Spec`] its diagonal comes from.
+///
+/// Defaults to [`KoalaBearPoseidon2Spec16`] so existing width-16 callers don't need to name a
+/// spec explicitly; width-24 callers should write `DiffusionMatrixKoalaBear<KoalaBearPoseidon2Spec24>`.
+#[derive(Debug, Clone)]
+pub struct DiffusionMatrixKoalaBear<Spec = KoalaBearPoseidon2Spec16> {
+    _spec: PhantomData<Spec>,
+}
+
+impl<Spec> Default for DiffusionMatrixKoalaBear<Spec> {
+    fn default() -> Self {
+        Self {
+            _spec: PhantomData,
+        }
+    }
+}
+
+impl<Spec, const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 16, D>
+    for DiffusionMatrixKoalaBear<Spec>
+where
+    Spec: Poseidon2Spec<KoalaBear, 16, D>,
 {
     type InternalState = [KoalaBear; 16];
 
@@ -114,16 +326,19 @@ impl<const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 1
         internal_constants: &[KoalaBear],
         _packed_internal_constants: &[<Poseidon2KoalaBearPackedConstants as Poseidon2PackedTypesAndConstants<KoalaBear, 16>>::InternalConstantsType],
     ) {
+        let shifts = diagonal_shifts(&Spec::internal_diagonal());
         internal_permute_state::<KoalaBear, 16, D>(
             state,
-            |x| permute_mut(x, &POSEIDON2_INTERNAL_MATRIX_DIAG_16_MONTY_SHIFTS),
+            |x| permute_mut(x, &shifts[1..]),
             internal_constants,
         )
     }
 }
 
-impl<const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 24, D>
-    for DiffusionMatrixKoalaBear
+impl<Spec, const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 24, D>
+    for DiffusionMatrixKoalaBear<Spec>
+where
+    Spec: Poseidon2Spec<KoalaBear, 24, D>,
 {
     type InternalState = [KoalaBear; 24];
 
@@ -133,14 +348,20 @@ impl<const D: u64> InternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, 2
         internal_constants: &[KoalaBear],
         _packed_internal_constants: &[<Poseidon2KoalaBearPackedConstants as Poseidon2PackedTypesAndConstants<KoalaBear, 24>>::InternalConstantsType],
     ) {
+        let shifts = diagonal_shifts(&Spec::internal_diagonal());
         internal_permute_state::<KoalaBear, 24, D>(
             state,
-            |x| permute_mut(x, &POSEIDON2_INTERNAL_MATRIX_DIAG_24_MONTY_SHIFTS),
+            |x| permute_mut(x, &shifts[1..]),
             internal_constants,
         )
     }
 }
 
+/// Unlike [`DiffusionMatrixKoalaBear`], this isn't made generic over a [`Poseidon2Spec`]:
+/// it has no hardcoded constants of its own to route through one. Its external round
+/// constants are plain runtime arguments to `permute_state_initial`/`permute_state_final`
+/// below, so a [`Poseidon2Spec`]'s generated constants already plug in directly wherever this
+/// type is used, e.g. via [`new_poseidon2_from_spec`].
 #[derive(Debug, Clone, Default)]
 pub struct MDSLightPermutationKoalaBear;
 
@@ -180,9 +401,148 @@ where
     }
 }
 
+/// Build a Poseidon2 permutation whose external and internal round constants come from
+/// `Spec`'s Grain-LFSR generation, rather than an RNG or a hand-transcribed table, and whose
+/// internal diffusion diagonal is `Spec::internal_diagonal`.
+pub fn new_poseidon2_from_spec<Spec, const WIDTH: usize, const D: u64>() -> Poseidon2<
+    KoalaBear,
+    MDSLightPermutationKoalaBear,
+    DiffusionMatrixKoalaBear<Spec>,
+    Poseidon2KoalaBearPackedConstants,
+    WIDTH,
+    D,
+>
+where
+    Spec: Poseidon2Spec<KoalaBear, WIDTH, D>,
+    Poseidon2KoalaBearPackedConstants: Poseidon2PackedTypesAndConstants<KoalaBear, WIDTH>,
+    MDSLightPermutationKoalaBear: ExternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, WIDTH, D>,
+    DiffusionMatrixKoalaBear<Spec>: InternalLayer<
+        KoalaBear,
+        Poseidon2KoalaBearPackedConstants,
+        WIDTH,
+        D,
+        InternalState = <MDSLightPermutationKoalaBear as ExternalLayer<
+            KoalaBear,
+            Poseidon2KoalaBearPackedConstants,
+            WIDTH,
+            D,
+        >>::InternalState,
+    >,
+{
+    let (mut external, internal) = Spec::round_constants();
+    let terminal = external.split_off(Spec::ROUNDS_F / 2);
+    let initial = external;
+    Poseidon2::new(
+        ExternalLayerConstants::new(initial, terminal),
+        MDSLightPermutationKoalaBear,
+        internal,
+        DiffusionMatrixKoalaBear::default(),
+    )
+}
+
+/// Which half of the duplex sponge's lifecycle it is currently in.
+///
+/// A sponge only ever moves forward through this cycle: once `squeeze` has run, `absorb` is
+/// no longer valid, since the rate lanes it would write into have already been read out as
+/// output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpongePhase {
+    Absorbing,
+    Squeezing,
+}
+
+/// A duplex sponge hash built on top of a Poseidon2 permutation, giving a variable-length,
+/// domain-separable hash (and, by squeezing fewer elements than were absorbed, a 2-to-1
+/// compressor for Merkle trees) without re-deriving the state management by hand.
+///
+/// The `WIDTH`-element permutation state is split into a `RATE`-element rate (the first
+/// `RATE` lanes, where input is absorbed and output is squeezed) and a `WIDTH - RATE` element
+/// capacity (the remaining lanes, zero-initialized and never touched directly). Input is
+/// padded with the standard `10*` scheme: a single `1` is added to the next free rate lane,
+/// and the rest of that block is left as zero.
+#[derive(Debug, Clone)]
+pub struct Poseidon2Sponge<Perm, const WIDTH: usize, const RATE: usize> {
+    permutation: Perm,
+    state: [KoalaBear; WIDTH],
+    // Index into `state[..RATE]` of the next lane to absorb into or squeeze from.
+    pos: usize,
+    phase: SpongePhase,
+}
+
+impl<Perm, const WIDTH: usize, const RATE: usize> Poseidon2Sponge<Perm, WIDTH, RATE> {
+    pub fn new(permutation: Perm) -> Self {
+        assert!(RATE < WIDTH, "the capacity must be non-empty");
+        Self {
+            permutation,
+            state: [KoalaBear::ZERO; WIDTH],
+            pos: 0,
+            phase: SpongePhase::Absorbing,
+        }
+    }
+}
+
+impl<Perm, const WIDTH: usize, const RATE: usize> Poseidon2Sponge<Perm, WIDTH, RATE>
+where
+    Perm: Permutation<[KoalaBear; WIDTH]>,
+{
+    /// Absorb `input` into the rate lanes, permuting the full state every time a rate block
+    /// fills up.
+    ///
+    /// # Panics
+    /// Panics if called after [`Self::squeeze`] has already run: interleaving absorb and
+    /// squeeze calls on the same sponge instance isn't supported.
+    pub fn absorb(&mut self, input: &[KoalaBear]) {
+        assert_eq!(
+            self.phase,
+            SpongePhase::Absorbing,
+            "cannot absorb once squeezing has started"
+        );
+        for &x in input {
+            self.state[self.pos] += x;
+            self.pos += 1;
+            if self.pos == RATE {
+                self.permutation.permute_mut(&mut self.state);
+                self.pos = 0;
+            }
+        }
+    }
+
+    /// Squeeze `n` output elements from the rate lanes, applying `10*` padding and a final
+    /// absorb-side permutation on the first call, then permuting again every time the rate
+    /// buffer is exhausted.
+    pub fn squeeze(&mut self, n: usize) -> Vec<KoalaBear> {
+        if self.phase == SpongePhase::Absorbing {
+            self.state[self.pos] += KoalaBear::ONE;
+            self.permutation.permute_mut(&mut self.state);
+            self.pos = 0;
+            self.phase = SpongePhase::Squeezing;
+        }
+
+        (0..n)
+            .map(|_| {
+                if self.pos == RATE {
+                    self.permutation.permute_mut(&mut self.state);
+                    self.pos = 0;
+                }
+                let out = self.state[self.pos];
+                self.pos += 1;
+                out
+            })
+            .collect()
+    }
+}
+
+/// A [`Poseidon2Sponge`] over the width-16 KoalaBear permutation, with an 8-element rate and
+/// an 8-element capacity.
+pub type Poseidon2SpongeKoalaBear16<Perm> = Poseidon2Sponge<Perm, 16, 8>;
+
+/// A [`Poseidon2Sponge`] over the width-24 KoalaBear permutation, with a 16-element rate and
+/// an 8-element capacity.
+pub type Poseidon2SpongeKoalaBear24<Perm> = Poseidon2Sponge<Perm, 24, 16>;
+
 #[cfg(test)]
 mod tests {
-    use p3_field::AbstractField;
+    use p3_field::FieldAlgebra;
     use p3_poseidon2::Poseidon2;
     use p3_symmetric::Permutation;
     use rand::SeedableRng;
@@ -196,12 +556,13 @@ mod tests {
     // See: https://github.com/0xPolygonZero/hash-constants for the sage code used to create all these tests.
 
     // Our Poseidon2 Implementation for KoalaBear
-    fn poseidon2_koalabear<const WIDTH: usize, const D: u64>(input: &mut [F; WIDTH])
+    fn poseidon2_koalabear<Spec, const WIDTH: usize, const D: u64>(input: &mut [F; WIDTH])
     where
+        Spec: Poseidon2Spec<KoalaBear, WIDTH, D>,
         Poseidon2KoalaBearPackedConstants: Poseidon2PackedTypesAndConstants<KoalaBear, WIDTH>,
         MDSLightPermutationKoalaBear:
             ExternalLayer<KoalaBear, Poseidon2KoalaBearPackedConstants, WIDTH, D>,
-        DiffusionMatrixKoalaBear: InternalLayer<
+        DiffusionMatrixKoalaBear<Spec>: InternalLayer<
             KoalaBear,
             Poseidon2KoalaBearPackedConstants,
             WIDTH,
@@ -220,13 +581,13 @@ mod tests {
         let poseidon2: Poseidon2<
             F,
             MDSLightPermutationKoalaBear,
-            DiffusionMatrixKoalaBear,
+            DiffusionMatrixKoalaBear<Spec>,
             Poseidon2KoalaBearPackedConstants,
             WIDTH,
             D,
         > = Poseidon2::new_from_rng_128(
             MDSLightPermutationKoalaBear,
-            DiffusionMatrixKoalaBear,
+            DiffusionMatrixKoalaBear::<Spec>::default(),
             &mut rng,
         );
 
@@ -253,7 +614,7 @@ mod tests {
         ]
         .map(F::from_canonical_u32);
 
-        poseidon2_koalabear::<16, 3>(&mut input);
+        poseidon2_koalabear::<KoalaBearPoseidon2Spec16, 16, 3>(&mut input);
         assert_eq!(input, expected);
     }
 
@@ -279,7 +640,307 @@ mod tests {
         ]
         .map(F::from_canonical_u32);
 
-        poseidon2_koalabear::<24, 3>(&mut input);
+        poseidon2_koalabear::<KoalaBearPoseidon2Spec24, 24, 3>(&mut input);
         assert_eq!(input, expected);
     }
+
+    // We need some round constants to build a Poseidon2 permutation to feed to the sponge.
+    // The exact constants don't matter for these tests, only that the sponge behaves
+    // correctly on top of whatever permutation it is given.
+    fn make_poseidon2_16() -> Poseidon2<
+        F,
+        MDSLightPermutationKoalaBear,
+        DiffusionMatrixKoalaBear,
+        Poseidon2KoalaBearPackedConstants,
+        16,
+        3,
+    > {
+        let mut rng = Xoroshiro128Plus::seed_from_u64(2);
+        Poseidon2::new_from_rng_128(
+            MDSLightPermutationKoalaBear,
+            DiffusionMatrixKoalaBear::default(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn test_sponge_is_deterministic() {
+        let input: Vec<F> = (0..10).map(F::from_canonical_u32).collect();
+
+        let mut sponge_a = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        sponge_a.absorb(&input);
+        let out_a = sponge_a.squeeze(8);
+
+        let mut sponge_b = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        sponge_b.absorb(&input);
+        let out_b = sponge_b.squeeze(8);
+
+        assert_eq!(out_a, out_b);
+    }
+
+    #[test]
+    fn test_sponge_distinguishes_empty_and_nonempty_input() {
+        let mut empty_sponge = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        let out_empty = empty_sponge.squeeze(8);
+
+        let mut nonempty_sponge = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        nonempty_sponge.absorb(&[F::ZERO]);
+        let out_nonempty = nonempty_sponge.squeeze(8);
+
+        // The `10*` padding means absorbing a single zero still differs from absorbing
+        // nothing at all, since it shifts where the padding `1` lands.
+        assert_ne!(out_empty, out_nonempty);
+    }
+
+    #[test]
+    fn test_sponge_handles_multiple_rate_blocks() {
+        let input: Vec<F> = (0..20).map(F::from_canonical_u32).collect();
+
+        let mut sponge = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        sponge.absorb(&input);
+        let out = sponge.squeeze(16);
+
+        assert_eq!(out.len(), 16);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sponge_rejects_absorb_after_squeeze() {
+        let mut sponge = Poseidon2SpongeKoalaBear16::new(make_poseidon2_16());
+        sponge.absorb(&[F::ONE]);
+        sponge.squeeze(4);
+        sponge.absorb(&[F::ONE]);
+    }
+
+    type Spec16 = KoalaBearPoseidon2Spec16;
+
+    #[test]
+    fn test_grain_generated_constants_are_valid_field_elements() {
+        let external = <Spec16 as Poseidon2Spec<F, 16, 3>>::external_round_constants();
+        assert_eq!(external.len(), <Spec16 as Poseidon2Spec<F, 16, 3>>::ROUNDS_F);
+
+        let internal = <Spec16 as Poseidon2Spec<F, 16, 3>>::internal_round_constants();
+        assert_eq!(internal.len(), <Spec16 as Poseidon2Spec<F, 16, 3>>::ROUNDS_P);
+
+        // `from_canonical_u32` would itself panic on an out-of-range value, but check
+        // explicitly that rejection sampling actually kept us under the modulus.
+        for x in internal {
+            assert!(x.as_canonical_u32() < KoalaBear::ORDER_U32);
+        }
+    }
+
+    #[test]
+    fn test_grain_generator_is_deterministic() {
+        let a = <Spec16 as Poseidon2Spec<F, 16, 3>>::internal_round_constants();
+        let b = <Spec16 as Poseidon2Spec<F, 16, 3>>::internal_round_constants();
+        assert_eq!(a, b);
+    }
+
+    type Spec24 = KoalaBearPoseidon2Spec24;
+
+    /// Regression pins for the Grain generator's output. These values aren't sourced from an
+    /// upstream reference (this sandbox has no network access to pull down the HorizenLabs
+    /// `generate_params_poseidon2.sage` script or any published KoalaBear constants to diff
+    /// against — there may be none, since this crate's width/round-count combination isn't
+    /// one any upstream source already publishes); see
+    /// [`test_grain_matches_independently_coded_reference`] for the actual cross-check this
+    /// generator gets. Catches regressions that change the stream without touching either
+    /// implementation, e.g. an accidental edit to the taps, the seed packing, or the
+    /// external/internal draw order.
+    #[test]
+    fn test_grain_generated_constants_match_known_answers() {
+        let external_16 = <Spec16 as Poseidon2Spec<F, 16, 3>>::external_round_constants();
+        assert_eq!(
+            external_16[0],
+            [
+                1636062357, 172489746, 822333578, 248360876, 400394015, 292372392, 278021376,
+                665722481, 1197388521, 1189822850, 918731583, 1796000611, 493953110, 161792673,
+                409868532, 643516950,
+            ]
+            .map(F::from_canonical_u32)
+        );
+
+        let internal_16 = <Spec16 as Poseidon2Spec<F, 16, 3>>::internal_round_constants();
+        assert_eq!(
+            internal_16[..3].to_vec(),
+            [1106406033, 268230756, 348576311]
+                .map(F::from_canonical_u32)
+                .to_vec()
+        );
+
+        let external_24 = <Spec24 as Poseidon2Spec<F, 24, 3>>::external_round_constants();
+        assert_eq!(
+            external_24[0],
+            [
+                1190105606, 859609501, 814459728, 2060468656, 710519923, 2002073146, 420220913,
+                1987232570, 726476102, 62903815, 150419528, 1411545472, 1541504538, 1187932107,
+                2114106083, 1632792862, 227339128, 2088688575, 484737491, 1669417454, 1691421689,
+                12457921, 2026167834, 1677130520,
+            ]
+            .map(F::from_canonical_u32)
+        );
+
+        let internal_24 = <Spec24 as Poseidon2Spec<F, 24, 3>>::internal_round_constants();
+        assert_eq!(
+            internal_24[..3].to_vec(),
+            [1802582497, 1085093283, 1062335653]
+                .map(F::from_canonical_u32)
+                .to_vec()
+        );
+    }
+
+    /// A second, independently-coded implementation of the same Grain self-shrinking
+    /// construction as [`GrainLfsr`], used only to cross-check
+    /// [`Poseidon2Spec::round_constants`] below. It represents the 80-bit register as a
+    /// `Vec<bool>` shifted with `remove(0)`/`push`, rather than `GrainLfsr`'s packed-`u128`
+    /// shifts, so a slip in the bit-shift arithmetic on one side won't silently reproduce
+    /// itself on the other.
+    ///
+    /// This only catches *transcription* bugs (the two implementations computing different
+    /// things from the same construction); it can't catch both sharing a mistaken
+    /// *understanding* of the construction, since both were written from the same reading of
+    /// the Grain-LFSR description in the Poseidon paper
+    /// (<https://eprint.iacr.org/2019/458>), not cross-checked against an upstream reference
+    /// implementation or published output (no network access here to fetch either — see
+    /// [`test_grain_generated_constants_match_known_answers`]).
+    struct ReferenceGrainLfsr {
+        state: Vec<bool>,
+    }
+
+    impl ReferenceGrainLfsr {
+        fn new(
+            sbox_degree: u64,
+            field_bits: u64,
+            width: u64,
+            rounds_f: u64,
+            rounds_p: u64,
+        ) -> Self {
+            let mut state = Vec::with_capacity(80);
+            let mut push = |value: u64, len: u32| {
+                for j in 0..len {
+                    state.push(((value >> (len - 1 - j)) & 1) == 1);
+                }
+            };
+            push(1, 2);
+            push(sbox_degree, 4);
+            push(field_bits, 12);
+            push(width, 12);
+            push(rounds_f, 10);
+            push(rounds_p, 10);
+            push((1 << 30) - 1, 30);
+            assert_eq!(state.len(), 80);
+
+            let mut lfsr = Self { state };
+            for _ in 0..160 {
+                lfsr.clock();
+            }
+            lfsr
+        }
+
+        fn clock(&mut self) -> bool {
+            let s = &self.state;
+            let feedback = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+            self.state.remove(0);
+            self.state.push(feedback);
+            feedback
+        }
+
+        fn next_bit(&mut self) -> bool {
+            loop {
+                let keep = self.clock();
+                let out = self.clock();
+                if keep {
+                    return out;
+                }
+            }
+        }
+
+        fn next_field_element(&mut self, field_bits: u32) -> u32 {
+            loop {
+                let mut candidate = 0u32;
+                for _ in 0..field_bits {
+                    candidate = (candidate << 1) | u32::from(self.next_bit());
+                }
+                if candidate < KoalaBear::ORDER_U32 {
+                    return candidate;
+                }
+            }
+        }
+
+        /// Generate round constants with the same draw discipline as
+        /// [`Poseidon2Spec::round_constants`]: `WIDTH` elements per external round, and
+        /// exactly one element per internal round, rather than `WIDTH` elements with all but
+        /// the first discarded. This matches the total constant count Poseidon2 parameter
+        /// generation is documented to use, `WIDTH * ROUNDS_F + ROUNDS_P`; drawing a full
+        /// vector per internal round and discarding the rest would instead total
+        /// `WIDTH * (ROUNDS_F + ROUNDS_P)` and desynchronize every constant drawn after the
+        /// first internal round, including every terminal external round.
+        fn round_constants<const WIDTH: usize>(
+            sbox_degree: u64,
+            rounds_f: usize,
+            rounds_p: usize,
+        ) -> (Vec<[u32; WIDTH]>, Vec<u32>) {
+            let field_bits = u32::BITS - KoalaBear::ORDER_U32.leading_zeros();
+            let mut lfsr = Self::new(
+                sbox_degree,
+                u64::from(field_bits),
+                WIDTH as u64,
+                rounds_f as u64,
+                rounds_p as u64,
+            );
+            let half_f = rounds_f / 2;
+            let mut external: Vec<[u32; WIDTH]> = (0..half_f)
+                .map(|_| core::array::from_fn(|_| lfsr.next_field_element(field_bits)))
+                .collect();
+            let internal: Vec<u32> = (0..rounds_p)
+                .map(|_| lfsr.next_field_element(field_bits))
+                .collect();
+            external.extend(
+                (0..half_f).map(|_| core::array::from_fn(|_| lfsr.next_field_element(field_bits))),
+            );
+            (external, internal)
+        }
+    }
+
+    #[test]
+    fn test_grain_matches_independently_coded_reference() {
+        let (ref_external_16, ref_internal_16) =
+            ReferenceGrainLfsr::round_constants::<16>(3, 8, 20);
+        let external_16 = <Spec16 as Poseidon2Spec<F, 16, 3>>::external_round_constants()
+            .iter()
+            .map(|round| round.map(|x| x.as_canonical_u32()))
+            .collect::<Vec<_>>();
+        assert_eq!(external_16, ref_external_16);
+
+        let internal_16 = <Spec16 as Poseidon2Spec<F, 16, 3>>::internal_round_constants()
+            .iter()
+            .map(|x| x.as_canonical_u32())
+            .collect::<Vec<_>>();
+        assert_eq!(internal_16, ref_internal_16);
+
+        let (ref_external_24, ref_internal_24) =
+            ReferenceGrainLfsr::round_constants::<24>(3, 8, 23);
+        let external_24 = <Spec24 as Poseidon2Spec<F, 24, 3>>::external_round_constants()
+            .iter()
+            .map(|round| round.map(|x| x.as_canonical_u32()))
+            .collect::<Vec<_>>();
+        assert_eq!(external_24, ref_external_24);
+
+        let internal_24 = <Spec24 as Poseidon2Spec<F, 24, 3>>::internal_round_constants()
+            .iter()
+            .map(|x| x.as_canonical_u32())
+            .collect::<Vec<_>>();
+        assert_eq!(internal_24, ref_internal_24);
+    }
+
+    #[test]
+    fn test_new_poseidon2_from_spec_permutes() {
+        let mut state_16 = [F::ZERO; 16];
+        new_poseidon2_from_spec::<Spec16, 16, 3>().permute_mut(&mut state_16);
+        assert_ne!(state_16, [F::ZERO; 16]);
+
+        let mut state_24 = [F::ZERO; 24];
+        new_poseidon2_from_spec::<Spec24, 24, 3>().permute_mut(&mut state_24);
+        assert_ne!(state_24, [F::ZERO; 24]);
+    }
 }
@@ -1,53 +1,171 @@
 use alloc::vec::Vec;
 
 use p3_field::{FieldAlgebra, TwoAdicField};
+use p3_maybe_rayon::prelude::*;
 use p3_symmetric::Permutation;
 use p3_util::{log2_strict_usize, reverse_slice_index_bits};
 
 use crate::butterflies::{dif_butterfly, dit_butterfly, twiddle_free_butterfly};
 use crate::MdsPermutation;
 
-/// A Reed-Solomon based MDS permutation.
+/// At or below this many elements, a Bowers network sub-transform runs with the flat unrolled
+/// loops below; above it, we recurse and dispatch the two halves across threads. Chosen so a
+/// base-case sub-transform's working set stays cache-resident; the butterfly math is
+/// unaffected, only the traversal order is.
+const PARALLEL_BOWERS_THRESHOLD: usize = 1 << 12;
+
+/// A Reed-Solomon based, runtime-sized low-degree-extension encoder.
+///
+/// Given `k` evaluations of a polynomial over a power-of-two subgroup, this computes
+/// `n = k << blowup_bits` evaluations over a coset of a `2^blowup_bits` times larger
+/// subgroup. Viewed as a linear map this is a generator matrix of a Reed-Solomon code of
+/// rate `k / n` (not in systematic form: the output holds only the coset evaluations, not
+/// the message itself); since Reed-Solomon codes are MDS, restricting to
+/// `blowup_bits = 0` (where `k == n`) recovers exactly the coset-twist MDS permutation that
+/// [`CosetMds`] used to hardcode.
 ///
-/// An MDS permutation which works by interpreting the input as evaluations of a polynomial over a
-/// power-of-two subgroup, and computing evaluations over a coset of that subgroup. This can be
-/// viewed as returning the parity elements of a systematic Reed-Solomon code. Since Reed-Solomon
-/// codes are MDS, this is an MDS permutation.
+/// This reuses [`bowers_g_t`] for the inverse transform (recovering coefficients from the
+/// `k`-sized message) and [`bowers_g`] for the forward transform (evaluating those
+/// coefficients, zero-extended, over the enlarged `n`-sized coset).
 #[derive(Clone, Debug)]
-pub struct CosetMds<F, const N: usize> {
+pub struct CosetRsEncoder<F> {
+    log_k: usize,
+    blowup_bits: usize,
+    /// Forward DFT twiddles for the enlarged, `n`-sized domain, bit-reversed.
     fft_twiddles: Vec<F>,
+    /// Inverse DFT twiddles for the `k`-sized message domain, bit-reversed.
     ifft_twiddles: Vec<F>,
-    weights: [F; N],
+    /// Powers of the coset shift, one per enlarged-domain element, bit-reversed to match the
+    /// bit-reversed output of `bowers_g_t`.
+    weights: Vec<F>,
 }
 
-impl<F, const N: usize> Default for CosetMds<F, N>
-where
-    F: TwoAdicField,
-{
-    fn default() -> Self {
-        let log_n = log2_strict_usize(N);
+impl<F: TwoAdicField> CosetRsEncoder<F> {
+    /// Create an encoder for messages of length `k`, extending them to `n = k << blowup_bits`
+    /// evaluations over a coset shifted by `F::GENERATOR`.
+    pub fn new(k: usize, blowup_bits: usize) -> Self {
+        Self::new_with_shift(k, blowup_bits, F::GENERATOR)
+    }
+
+    /// Like [`Self::new`], but with an explicit coset shift.
+    pub fn new_with_shift(k: usize, blowup_bits: usize, shift: F) -> Self {
+        let log_k = log2_strict_usize(k);
+        let log_n = log_k + blowup_bits;
+        let n = k << blowup_bits;
 
         let root = F::two_adic_generator(log_n);
-        let root_inv = root.inverse();
-        let mut fft_twiddles: Vec<F> = root.powers().take(N / 2).collect();
-        let mut ifft_twiddles: Vec<F> = root_inv.powers().take(N / 2).collect();
+        let mut fft_twiddles: Vec<F> = root.powers().take(n / 2).collect();
         reverse_slice_index_bits(&mut fft_twiddles);
+
+        let root_inv = F::two_adic_generator(log_k).inverse();
+        let mut ifft_twiddles: Vec<F> = root_inv.powers().take(k / 2).collect();
         reverse_slice_index_bits(&mut ifft_twiddles);
 
-        let shift = F::GENERATOR;
-        let mut weights: [F; N] = shift
-            .powers()
-            .take(N)
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap();
+        let mut weights: Vec<F> = shift.powers().take(n).collect();
         reverse_slice_index_bits(&mut weights);
+
         Self {
+            log_k,
+            blowup_bits,
             fft_twiddles,
             ifft_twiddles,
             weights,
         }
     }
+
+    /// Length of the input message.
+    pub fn k(&self) -> usize {
+        1 << self.log_k
+    }
+
+    /// Length of the encoded output, `k << blowup_bits`.
+    pub fn n(&self) -> usize {
+        self.k() << self.blowup_bits
+    }
+
+    /// Encode a single message of length [`Self::k`] into a fresh vector of length
+    /// [`Self::n`].
+    pub fn encode<FA: FieldAlgebra<F = F>>(&self, message: &[FA]) -> Vec<FA> {
+        assert_eq!(message.len(), self.k());
+        let mut buffer = message.to_vec();
+        buffer.resize(self.n(), FA::ZERO);
+        self.encode_in_place(&mut buffer);
+        buffer
+    }
+
+    /// Encode a single message in place. `buffer` must have length [`Self::n`], with the
+    /// message occupying the first [`Self::k`] entries and the rest zeroed.
+    pub fn encode_in_place<FA: FieldAlgebra<F = F>>(&self, buffer: &mut [FA]) {
+        assert_eq!(buffer.len(), self.n());
+        let k = self.k();
+        let scale = 1usize << self.blowup_bits;
+
+        // Inverse DFT over the size-`k` message domain to recover coefficients, skipping
+        // bit reversal and the `1/k` rescaling (as in the original `CosetMds`).
+        bowers_g_t(&mut buffer[..k], &self.ifft_twiddles);
+
+        // The coefficients above are in bit-reversed order for a size-`k` transform; scatter
+        // them into their bit-reversed positions for a size-`n` transform (the higher-degree
+        // coefficients of a degree-<k polynomial, viewed as a degree-<n one, are zero). For
+        // `scale == 1` (`blowup_bits == 0`) this is a no-op, recovering the original
+        // full-width `CosetMds` behavior exactly.
+        for p in (0..k).rev() {
+            let val = core::mem::replace(&mut buffer[p], FA::ZERO);
+            buffer[p * scale] = val;
+        }
+        for (i, value) in buffer.iter_mut().enumerate() {
+            if i % scale != 0 {
+                *value = FA::ZERO;
+            }
+        }
+
+        // Multiply by powers of the coset shift (see default coset LDE impl for an
+        // explanation).
+        for (value, &weight) in buffer.iter_mut().zip(&self.weights) {
+            *value = value.clone() * FA::from_f(weight);
+        }
+
+        // DFT, assuming bit-reversed input, over the enlarged domain.
+        bowers_g(buffer, &self.fft_twiddles);
+    }
+
+    /// Encode many messages, one row after another, each with [`Self::encode_in_place`] and
+    /// this encoder's precomputed twiddles and weights. The rows are not transformed together
+    /// in a single pass.
+    ///
+    /// `messages` and `encoded` are laid out as consecutive rows of length [`Self::k`] and
+    /// [`Self::n`] respectively (e.g. the rows of a `RowMajorMatrix`).
+    pub fn encode_batch<FA: FieldAlgebra<F = F>>(&self, messages: &[FA], encoded: &mut [FA]) {
+        let k = self.k();
+        let n = self.n();
+        assert_eq!(messages.len() % k, 0);
+        assert_eq!(encoded.len(), messages.len() / k * n);
+
+        for (msg_row, out_row) in messages.chunks_exact(k).zip(encoded.chunks_exact_mut(n)) {
+            out_row[..k].clone_from_slice(msg_row);
+            out_row[k..].fill(FA::ZERO);
+            self.encode_in_place(out_row);
+        }
+    }
+}
+
+/// An MDS permutation which works by interpreting the input as evaluations of a polynomial
+/// over a power-of-two subgroup, and computing evaluations over a coset of that subgroup.
+/// This can be viewed as returning the parity elements of a systematic Reed-Solomon code.
+/// Since Reed-Solomon codes are MDS, this is an MDS permutation.
+///
+/// This is the `blowup_bits = 0`, `k == n == N` special case of [`CosetRsEncoder`]: rather
+/// than extending a shorter message, it re-evaluates all `N` inputs over a same-size coset.
+#[derive(Clone, Debug)]
+pub struct CosetMds<F, const N: usize>(CosetRsEncoder<F>);
+
+impl<F, const N: usize> Default for CosetMds<F, N>
+where
+    F: TwoAdicField,
+{
+    fn default() -> Self {
+        Self(CosetRsEncoder::new(N, 0))
+    }
 }
 
 impl<FA, const N: usize> Permutation<[FA; N]> for CosetMds<FA::F, N>
@@ -61,16 +179,7 @@ where
     }
 
     fn permute_mut(&self, values: &mut [FA; N]) {
-        // Inverse DFT, except we skip bit reversal and rescaling by 1/N.
-        bowers_g_t(values, &self.ifft_twiddles);
-
-        // Multiply by powers of the coset shift (see default coset LDE impl for an explanation)
-        for (value, weight) in values.iter_mut().zip(self.weights) {
-            *value = value.clone() * FA::from_f(weight);
-        }
-
-        // DFT, assuming bit-reversed input.
-        bowers_g(values, &self.fft_twiddles);
+        self.0.encode_in_place(values);
     }
 }
 
@@ -83,76 +192,185 @@ where
 
 /// Executes the Bowers G network. This is like a DFT, except it assumes the input is in
 /// bit-reversed order.
+///
+/// Above [`PARALLEL_BOWERS_THRESHOLD`], this recursively splits the array in half, runs all
+/// but the final (full-width) layer on each half depth-first (and, while large enough, in
+/// parallel), then finishes with the combining layer across the whole array. Since a Bowers
+/// G layer's blocks for `log_half_block_size < log_n - 1` never straddle the halfway point,
+/// this produces results bit-for-bit identical to the flat, serial traversal.
 #[inline]
-fn bowers_g<FA: FieldAlgebra, const N: usize>(values: &mut [FA; N], twiddles: &[FA::F]) {
-    let log_n = log2_strict_usize(N);
-    for log_half_block_size in 0..log_n {
-        bowers_g_layer(values, log_half_block_size, twiddles);
+fn bowers_g<FA: FieldAlgebra>(values: &mut [FA], twiddles: &[FA::F]) {
+    let log_n = log2_strict_usize(values.len());
+    bowers_g_recursive(values, log_n, twiddles, 0, PARALLEL_BOWERS_THRESHOLD);
+}
+
+fn bowers_g_recursive<FA: FieldAlgebra>(
+    values: &mut [FA],
+    log_n: usize,
+    twiddles: &[FA::F],
+    sibling_index: usize,
+    threshold: usize,
+) {
+    if log_n == 0 {
+        return;
+    }
+    if values.len() <= threshold {
+        for log_half_block_size in 0..log_n {
+            bowers_g_layer(values, log_half_block_size, twiddles, sibling_index);
+        }
+        return;
     }
+
+    let half = values.len() / 2;
+    let (left, right) = values.split_at_mut(half);
+    join(
+        || bowers_g_recursive(left, log_n - 1, twiddles, sibling_index * 2, threshold),
+        || bowers_g_recursive(right, log_n - 1, twiddles, sibling_index * 2 + 1, threshold),
+    );
+    bowers_g_layer(values, log_n - 1, twiddles, sibling_index);
 }
 
 /// Executes the Bowers G^T network. This is like an inverse DFT, except we skip rescaling by
 /// `1/N`, and the output is bit-reversed.
+///
+/// Mirrors [`bowers_g_recursive`]: the full-width combining layer (here the *first* layer,
+/// since `bowers_g_t` walks layers from coarsest to finest) runs before recursing into the
+/// two halves for the remaining, narrower layers.
 #[inline]
-fn bowers_g_t<FA: FieldAlgebra, const N: usize>(values: &mut [FA; N], twiddles: &[FA::F]) {
-    let log_n = log2_strict_usize(N);
-    for log_half_block_size in (0..log_n).rev() {
-        bowers_g_t_layer(values, log_half_block_size, twiddles);
+fn bowers_g_t<FA: FieldAlgebra>(values: &mut [FA], twiddles: &[FA::F]) {
+    let log_n = log2_strict_usize(values.len());
+    bowers_g_t_recursive(values, log_n, twiddles, 0, PARALLEL_BOWERS_THRESHOLD);
+}
+
+fn bowers_g_t_recursive<FA: FieldAlgebra>(
+    values: &mut [FA],
+    log_n: usize,
+    twiddles: &[FA::F],
+    sibling_index: usize,
+    threshold: usize,
+) {
+    if log_n == 0 {
+        return;
     }
+    if values.len() <= threshold {
+        for log_half_block_size in (0..log_n).rev() {
+            bowers_g_t_layer(values, log_half_block_size, twiddles, sibling_index);
+        }
+        return;
+    }
+
+    bowers_g_t_layer(values, log_n - 1, twiddles, sibling_index);
+    let half = values.len() / 2;
+    let (left, right) = values.split_at_mut(half);
+    join(
+        || bowers_g_t_recursive(left, log_n - 1, twiddles, sibling_index * 2, threshold),
+        || bowers_g_t_recursive(right, log_n - 1, twiddles, sibling_index * 2 + 1, threshold),
+    );
 }
 
 /// One layer of a Bowers G network. Equivalent to `bowers_g_t_layer` except for the butterfly.
+///
+/// `sibling_index` identifies which same-size sibling slice `values` is, among all slices at
+/// its recursion depth (0 for the leftmost, increasing left-to-right); it's 0 for a top-level,
+/// non-recursive call. This is used, together with the block count implied by `values.len()`,
+/// to recover each block's twiddle factor from the single twiddle table shared by every
+/// layer and every recursive split.
 #[inline]
-fn bowers_g_layer<FA: FieldAlgebra, const N: usize>(
-    values: &mut [FA; N],
+fn bowers_g_layer<FA: FieldAlgebra>(
+    values: &mut [FA],
     log_half_block_size: usize,
     twiddles: &[FA::F],
+    sibling_index: usize,
 ) {
     let log_block_size = log_half_block_size + 1;
     let half_block_size = 1 << log_half_block_size;
-    let num_blocks = N >> log_block_size;
+    let num_blocks = values.len() >> log_block_size;
+    let block_offset = sibling_index * num_blocks;
 
-    // Unroll first iteration with a twiddle factor of 1.
-    for hi in 0..half_block_size {
-        let lo = hi + half_block_size;
-        twiddle_free_butterfly(values, hi, lo);
-    }
-
-    for (block, &twiddle) in (1..num_blocks).zip(&twiddles[1..]) {
-        let block_start = block << log_block_size;
-        for hi in block_start..block_start + half_block_size {
+    if block_offset == 0 {
+        // Unroll first iteration with a twiddle factor of 1.
+        for hi in 0..half_block_size {
             let lo = hi + half_block_size;
-            dif_butterfly(values, hi, lo, twiddle);
+            twiddle_free_butterfly(values, hi, lo);
+        }
+
+        for (block, &twiddle) in (1..num_blocks).zip(&twiddles[1..]) {
+            let block_start = block << log_block_size;
+            for hi in block_start..block_start + half_block_size {
+                let lo = hi + half_block_size;
+                dif_butterfly(values, hi, lo, twiddle);
+            }
+        }
+    } else {
+        for (block, &twiddle) in (0..num_blocks).zip(&twiddles[block_offset..]) {
+            let block_start = block << log_block_size;
+            for hi in block_start..block_start + half_block_size {
+                let lo = hi + half_block_size;
+                dif_butterfly(values, hi, lo, twiddle);
+            }
         }
     }
 }
 
 /// One layer of a Bowers G^T network. Equivalent to `bowers_g_layer` except for the butterfly.
+/// See `bowers_g_layer` for what `sibling_index` means.
 #[inline]
-fn bowers_g_t_layer<FA: FieldAlgebra, const N: usize>(
-    values: &mut [FA; N],
+fn bowers_g_t_layer<FA: FieldAlgebra>(
+    values: &mut [FA],
     log_half_block_size: usize,
     twiddles: &[FA::F],
+    sibling_index: usize,
 ) {
     let log_block_size = log_half_block_size + 1;
     let half_block_size = 1 << log_half_block_size;
-    let num_blocks = N >> log_block_size;
-
-    // Unroll first iteration with a twiddle factor of 1.
-    for hi in 0..half_block_size {
-        let lo = hi + half_block_size;
-        twiddle_free_butterfly(values, hi, lo);
-    }
+    let num_blocks = values.len() >> log_block_size;
+    let block_offset = sibling_index * num_blocks;
 
-    for (block, &twiddle) in (1..num_blocks).zip(&twiddles[1..]) {
-        let block_start = block << log_block_size;
-        for hi in block_start..block_start + half_block_size {
+    if block_offset == 0 {
+        // Unroll first iteration with a twiddle factor of 1.
+        for hi in 0..half_block_size {
             let lo = hi + half_block_size;
-            dit_butterfly(values, hi, lo, twiddle);
+            twiddle_free_butterfly(values, hi, lo);
+        }
+
+        for (block, &twiddle) in (1..num_blocks).zip(&twiddles[1..]) {
+            let block_start = block << log_block_size;
+            for hi in block_start..block_start + half_block_size {
+                let lo = hi + half_block_size;
+                dit_butterfly(values, hi, lo, twiddle);
+            }
+        }
+    } else {
+        for (block, &twiddle) in (0..num_blocks).zip(&twiddles[block_offset..]) {
+            let block_start = block << log_block_size;
+            for hi in block_start..block_start + half_block_size {
+                let lo = hi + half_block_size;
+                dit_butterfly(values, hi, lo, twiddle);
+            }
         }
     }
 }
 
+#[cfg(test)]
+fn bowers_g_with_threshold<FA: FieldAlgebra>(
+    values: &mut [FA],
+    twiddles: &[FA::F],
+    threshold: usize,
+) {
+    let log_n = log2_strict_usize(values.len());
+    bowers_g_recursive(values, log_n, twiddles, 0, threshold);
+}
+
+#[cfg(test)]
+fn bowers_g_t_with_threshold<FA: FieldAlgebra>(
+    values: &mut [FA],
+    twiddles: &[FA::F],
+    threshold: usize,
+) {
+    let log_n = log2_strict_usize(values.len());
+    bowers_g_t_recursive(values, log_n, twiddles, 0, threshold);
+}
+
 #[cfg(test)]
 mod tests {
     use p3_baby_bear::BabyBear;
@@ -161,7 +379,9 @@ mod tests {
     use p3_symmetric::Permutation;
     use rand::{thread_rng, Rng};
 
-    use crate::coset_mds::CosetMds;
+    use crate::coset_mds::{
+        bowers_g_t_with_threshold, bowers_g_with_threshold, CosetMds, CosetRsEncoder,
+    };
 
     #[test]
     fn matches_naive() {
@@ -177,4 +397,74 @@ mod tests {
         CosetMds::default().permute_mut(&mut arr);
         assert_eq!(coset_lde_naive, arr);
     }
+
+    #[test]
+    fn coset_rs_encoder_matches_naive_with_blowup() {
+        type F = BabyBear;
+        const K: usize = 8;
+        const BLOWUP_BITS: usize = 2;
+
+        let mut rng = thread_rng();
+        let message: Vec<F> = (0..K).map(|_| rng.gen()).collect();
+
+        let shift = F::GENERATOR;
+        let mut lde_naive = NaiveDft.coset_lde(message.clone(), BLOWUP_BITS, shift);
+        lde_naive.iter_mut().for_each(|x| *x *= (K as u64).into());
+
+        let encoder = CosetRsEncoder::new(K, BLOWUP_BITS);
+        let encoded = encoder.encode(&message);
+
+        assert_eq!(encoded.len(), K << BLOWUP_BITS);
+        assert_eq!(lde_naive, encoded);
+    }
+
+    #[test]
+    fn coset_rs_encoder_batch_matches_single() {
+        type F = BabyBear;
+        const K: usize = 8;
+        const BLOWUP_BITS: usize = 1;
+
+        let mut rng = thread_rng();
+        let messages: Vec<Vec<F>> = (0..3)
+            .map(|_| (0..K).map(|_| rng.gen()).collect())
+            .collect();
+
+        let encoder = CosetRsEncoder::new(K, BLOWUP_BITS);
+        let n = K << BLOWUP_BITS;
+
+        let flat_messages: Vec<F> = messages.iter().flatten().copied().collect();
+        let mut batched: Vec<F> = (0..messages.len() * n).map(|_| F::ZERO).collect();
+        encoder.encode_batch(&flat_messages, &mut batched);
+
+        for (message, expected) in messages.iter().zip(batched.chunks_exact(n)) {
+            assert_eq!(encoder.encode(message), expected);
+        }
+    }
+
+    /// Forcing a tiny parallel-dispatch threshold should have no effect on the result: the
+    /// recursive traversal must match the flat, serial one bit-for-bit, across several
+    /// widths.
+    #[test]
+    fn recursive_bowers_matches_serial_across_widths() {
+        type F = BabyBear;
+
+        let mut rng = thread_rng();
+        for log_n in [3, 4, 6, 8] {
+            let n = 1 << log_n;
+            let arr: Vec<F> = (0..n).map(|_| rng.gen()).collect();
+            let twiddles: Vec<F> = (0..n / 2).map(|_| rng.gen()).collect();
+
+            let mut serial = arr.clone();
+            bowers_g_with_threshold(&mut serial, &twiddles, usize::MAX);
+            let mut parallel = arr.clone();
+            bowers_g_with_threshold(&mut parallel, &twiddles, 1);
+            assert_eq!(serial, parallel, "bowers_g mismatch at n = {n}");
+
+            let mut serial = arr.clone();
+            bowers_g_t_with_threshold(&mut serial, &twiddles, usize::MAX);
+            let mut parallel = arr;
+            bowers_g_t_with_threshold(&mut parallel, &twiddles, 1);
+            assert_eq!(serial, parallel, "bowers_g_t mismatch at n = {n}");
+        }
+    }
 }